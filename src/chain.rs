@@ -0,0 +1,45 @@
+//! Chain parameters needed to build and validate the eth `Status` handshake message.
+
+/// Minimal chain parameters needed to populate our `Status` message and compute our fork id.
+///
+/// Only mainnet is wired up for now; other networks can be added as additional
+/// constructors once this crate needs to target them.
+#[derive(Clone)]
+pub struct ChainInfo {
+    pub network_id: u64,
+    pub genesis_hash: [u8; 32],
+    pub total_difficulty: u128,
+    pub best_block_hash: [u8; 32],
+    pub fork_blocks: Vec<u64>,
+    pub head: u64,
+}
+
+impl ChainInfo {
+    /// Ethereum mainnet, advertising the genesis block as our head.
+    ///
+    /// A real crawler would track its own synced head; until this crate follows the
+    /// chain, we advertise genesis so a peer can still validate our fork id against us.
+    pub fn mainnet() -> Self {
+        Self {
+            network_id: 1,
+            genesis_hash: hex_to_32("d4e56740f876aef8c010b86a40d5f56745a118d0906a34e69aec8c0db1cb8fa3"),
+            total_difficulty: 0,
+            best_block_hash: hex_to_32(
+                "d4e56740f876aef8c010b86a40d5f56745a118d0906a34e69aec8c0db1cb8fa3",
+            ),
+            fork_blocks: vec![
+                1_150_000, 1_920_000, 2_463_000, 2_675_000, 4_370_000, 7_280_000, 9_069_000,
+                9_200_000, 12_244_000, 12_965_000, 13_773_000, 15_050_000,
+            ],
+            head: 0,
+        }
+    }
+}
+
+fn hex_to_32(hex: &str) -> [u8; 32] {
+    let mut out = [0u8; 32];
+    for (i, byte) in out.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16).expect("valid hex literal");
+    }
+    out
+}
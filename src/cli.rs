@@ -1,10 +1,48 @@
 use clap::Parser;
+use std::path::PathBuf;
+
 /// Struct for parsing command line arguments
 #[derive(Parser, Debug)]
 #[command(name = "Node Connector")]
 #[command(about = "A CLI for connecting to an Ethereum node", long_about = None)]
 pub struct Cli {
-    /// NodeRecord string
+    /// NodeRecord string(s) to dial (enode://...@host:port). May be repeated to scan
+    /// several peers concurrently. Ignored when `--discover` is set.
     #[arg(long)]
-    pub node_record: String,
+    pub node_record: Vec<String>,
+
+    /// Path to a file with one enode:// URL per line, merged with `--node-record`.
+    /// Blank lines and lines starting with '#' are ignored.
+    #[arg(long)]
+    pub node_record_file: Option<PathBuf>,
+
+    /// Maximum number of handshakes to run concurrently.
+    #[arg(long, default_value_t = 10)]
+    pub concurrency: usize,
+
+    /// Emit the scan summary as JSON instead of human-readable text.
+    #[arg(long)]
+    pub json: bool,
+
+    /// Discover peers via discv4 instead of dialing node records directly.
+    #[arg(long)]
+    pub discover: bool,
+
+    /// Override the default discv4 boot nodes (enode:// URLs). May be repeated.
+    #[arg(long)]
+    pub bootnodes: Vec<String>,
+
+    /// Number of peers to discover and handshake with before exiting, in `--discover` mode.
+    #[arg(long, default_value_t = 10)]
+    pub target_peers: usize,
+
+    /// Bind a TCP listener on this address and accept inbound handshakes instead of
+    /// dialing out. Mutually exclusive with `--node-record` / `--discover`.
+    #[arg(long, conflicts_with_all = ["node_record", "discover"])]
+    pub listen: Option<String>,
+
+    /// Ping interval in seconds. When set, runs a Hello + Ping/Pong keepalive session
+    /// against the first `--node-record` instead of a one-shot handshake probe.
+    #[arg(long)]
+    pub keepalive: Option<u64>,
 }
@@ -7,3 +7,14 @@ pub const TIMEOUT: u64 = 10;
 /// This constant is used to validate the format of node record strings, which should
 /// contain exactly two parts: the node identifier and the address with port, separated by '@'.
 pub const ETH_EXPECTED_PARTS_LEN: usize = 2;
+
+/// The devp2p base protocol versions we know how to speak. A Hello advertising anything
+/// outside this range isn't a peer on an older or newer protocol we merely don't support
+/// yet - devp2p has only ever specified versions 4 and 5 - so it's treated as malicious.
+pub const MIN_P2P_PROTOCOL_VERSION: u8 = 4;
+pub const MAX_P2P_PROTOCOL_VERSION: u8 = 5;
+
+/// The largest single P2P frame we'll send or accept, in bytes. Matches the de facto
+/// devp2p convention (also used by go-ethereum and reth) of capping messages at 16 MiB
+/// so a peer can't force unbounded buffer growth with one oversized frame.
+pub const MAX_FRAME_SIZE: usize = 16 * 1024 * 1024;
@@ -0,0 +1,91 @@
+//! Peer discovery over discv4.
+//!
+//! Bootstraps against a set of well-known boot nodes and continuously yields
+//! [`NodeRecord`]s for the caller to dial and handshake with, instead of requiring a
+//! single hand-typed node record up front.
+
+use crate::errors::CustomError;
+
+use futures::StreamExt;
+use reth_discv4::{Discv4, Discv4Config, DiscoveryUpdate, DEFAULT_DISCOVERY_PORT};
+use reth_network_peers::NodeRecord;
+use secp256k1::SecretKey;
+use std::net::SocketAddr;
+use std::str::FromStr;
+use tokio::sync::mpsc;
+
+/// Ethereum mainnet discv4 boot nodes, used when the caller doesn't override them with
+/// `--bootnodes`.
+pub const MAINNET_BOOTNODES: &[&str] = &[
+    "enode://d860a01f9722d78051619d1e2351aba3f43f943f6f00718d1b9baa4101932a1f5011f16bb2b1bb35db20d6fe28fa0bf09636d26a87d31de9ec6203eeedb1f666@18.138.108.67:30303",
+    "enode://22a8232c3abc76a16ae9d6c3b164f98775fe226f0917b0ca871128a74a8e9630b458460865bab457221f1d448dd9791d24c4e5d88786180ac185df813a68d4de@3.209.45.79:30303",
+    "enode://2b252ab6a1d0f971d9722cb839a42cb81db019ba44c08754628ab4a823487071b5695317c8ccd085219c3a03af063495b2f1da8d18218da2d6a82981b45e6ffc@65.108.70.101:30303",
+    "enode://4aeb4ab6c14b23e2c4cfdce879c04b0748a20d8e9b59e25ded2a08143e265c6c25936e74cbc8e641f3f37f6aa9b3c6e2c94e7c96a5e02d5e2c46d76c1e4b6fc4@157.90.35.166:30303",
+];
+
+/// Starts a discv4 discovery service bootstrapped against `bootnodes` (or
+/// [`MAINNET_BOOTNODES`] if empty), and returns a receiver that yields discovered
+/// [`NodeRecord`]s as they're found.
+///
+/// The channel is bounded by `queue_size` so a caller that dials slower than discovery
+/// finds peers applies natural backpressure instead of letting discovered records pile
+/// up unbounded.
+///
+/// # Arguments
+///
+/// * `secret_key` - The identity discv4 uses to sign its own packets.
+/// * `bootnodes` - Enode URLs to bootstrap against; falls back to mainnet defaults if empty.
+/// * `queue_size` - The bound on the discovered-record channel.
+///
+/// # Returns
+///
+/// A receiver yielding discovered node records, or a `CustomError::Unexpected` if the
+/// discv4 service fails to start.
+pub async fn discover_peers(
+    secret_key: &SecretKey,
+    bootnodes: &[String],
+    queue_size: usize,
+) -> Result<mpsc::Receiver<NodeRecord>, CustomError> {
+    let bootnode_urls: Vec<&str> = if bootnodes.is_empty() {
+        MAINNET_BOOTNODES.to_vec()
+    } else {
+        bootnodes.iter().map(String::as_str).collect()
+    };
+
+    let boot_records = bootnode_urls
+        .iter()
+        .map(|url| NodeRecord::from_str(url).map_err(CustomError::from))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let discv4_addr: SocketAddr = format!("0.0.0.0:{DEFAULT_DISCOVERY_PORT}")
+        .parse()
+        .expect("static discovery address is valid");
+
+    let mut config = Discv4Config::builder();
+    config.add_boot_nodes(boot_records);
+
+    let (discv4, mut service) =
+        Discv4::bind(discv4_addr, discv4_addr, *secret_key, config.build())
+            .await
+            .map_err(|e| CustomError::Unexpected(format!("failed to bind discv4: {e}")))?;
+
+    let mut updates = discv4
+        .update_stream()
+        .await
+        .map_err(|e| CustomError::Unexpected(format!("failed to subscribe to discv4 updates: {e}")))?;
+
+    let (tx, rx) = mpsc::channel(queue_size);
+
+    tokio::spawn(async move { service.run().await });
+    tokio::spawn(async move {
+        while let Some(update) = updates.next().await {
+            if let DiscoveryUpdate::Added(record) = update {
+                if tx.send(record).await.is_err() {
+                    break;
+                }
+            }
+        }
+    });
+
+    Ok(rx)
+}
@@ -1,52 +1,72 @@
-use alloy_rlp;
-use reth_network_peers;
+//! Error taxonomy for the application.
+//!
+//! Variants are grouped by how a caller should react, not by which function raised
+//! them: `Timeout`, `Io`, and `ConnectionClosed` are transient and worth retrying;
+//! `Deserialization` means we received bytes we couldn't parse; `Malicious` is a peer
+//! actively misbehaving, carrying enough context to record-and-skip it via the
+//! reputation subsystem; `Unexpected` covers everything else.
+
 use std::io;
 use thiserror::Error;
-use tokio::time::error::Elapsed;
+
+/// Context describing how a peer actively misbehaved, as opposed to a merely transient
+/// fault like a dropped connection or a slow reply.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum MaliciousReason {
+    #[error("peer sent {0} before completing the Hello handshake")]
+    MessageBeforeHello(String),
+    #[error("peer advertised an impossible protocol version: {0}")]
+    ImpossibleProtocolVersion(u8),
+    #[error("peer's eth Status did not match ours: {0}")]
+    StatusMismatch(String),
+    #[error("peer frame of {actual} bytes exceeded the {max} byte limit")]
+    FrameTooLarge { actual: usize, max: usize },
+}
 
 #[derive(Debug, Error)]
 pub enum CustomError {
-    #[error("Failed to extract address and port from node record")]
-    AddressPortParse,
-    #[error("Failed to connect to the TCP stream: {0}")]
-    TcpConnectTimeOut(String),
-    #[error("Failed to connect to the TCP stream: {0}")]
-    TcpConnect(#[from] io::Error),
-    #[error("Failed to create NodeRecord from string: {0}")]
-    NodeRecordCreation(#[from] reth_network_peers::NodeRecordParseError),
-    #[error("Failed to create ECIES stream")]
-    ECIESStreamCreation,
-    #[error("Failed to send message")]
-    SendMessage,
-    #[error("Failed to receive message")]
-    ReceiveMessage,
-    #[error("Failed to decode P2P message: {0}")]
-    MessageDecode(#[from] alloy_rlp::Error),
+    #[error("Operation timed out: {0}")]
+    Timeout(String),
+    #[error("I/O error: {0}")]
+    Io(#[from] io::Error),
+    #[error("Failed to deserialize peer data: {0}")]
+    Deserialization(String),
+    #[error("Peer misbehaved: {0}")]
+    Malicious(MaliciousReason),
+    #[error("Unexpected error: {0}")]
+    Unexpected(String),
+    #[error("Connection closed")]
+    ConnectionClosed,
+}
+
+impl From<alloy_rlp::Error> for CustomError {
+    fn from(e: alloy_rlp::Error) -> Self {
+        CustomError::Deserialization(e.to_string())
+    }
+}
+
+impl From<reth_network_peers::NodeRecordParseError> for CustomError {
+    fn from(e: reth_network_peers::NodeRecordParseError) -> Self {
+        CustomError::Unexpected(format!("failed to parse node record: {e}"))
+    }
 }
 
-impl From<Elapsed> for CustomError {
-    fn from(_: Elapsed) -> Self {
-        CustomError::TcpConnectTimeOut("Timeout".to_string())
+impl From<tokio::time::error::Elapsed> for CustomError {
+    fn from(_: tokio::time::error::Elapsed) -> Self {
+        CustomError::Timeout("operation timed out".to_string())
     }
 }
 
 impl PartialEq for CustomError {
     fn eq(&self, other: &Self) -> bool {
-        matches!(
-            (self, other),
-            (CustomError::AddressPortParse, CustomError::AddressPortParse)
-                | (CustomError::TcpConnect(_), CustomError::TcpConnect(_))
-                | (
-                    CustomError::NodeRecordCreation(_),
-                    CustomError::NodeRecordCreation(_)
-                )
-                | (
-                    CustomError::ECIESStreamCreation,
-                    CustomError::ECIESStreamCreation
-                )
-                | (CustomError::SendMessage, CustomError::SendMessage)
-                | (CustomError::ReceiveMessage, CustomError::ReceiveMessage)
-                | (CustomError::MessageDecode(_), CustomError::MessageDecode(_))
-        )
+        match (self, other) {
+            (CustomError::Timeout(_), CustomError::Timeout(_)) => true,
+            (CustomError::Io(_), CustomError::Io(_)) => true,
+            (CustomError::Deserialization(_), CustomError::Deserialization(_)) => true,
+            (CustomError::Malicious(a), CustomError::Malicious(b)) => a == b,
+            (CustomError::Unexpected(_), CustomError::Unexpected(_)) => true,
+            (CustomError::ConnectionClosed, CustomError::ConnectionClosed) => true,
+            _ => false,
+        }
     }
 }
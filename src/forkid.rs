@@ -0,0 +1,143 @@
+//! EIP-2124 fork identifier computation and validation for the eth `Status` handshake.
+//!
+//! A `ForkId` lets a node advertise the forks it has activated so a peer can tell
+//! whether it is compatible with our chain without exchanging a full fork list.
+//! See <https://eips.ethereum.org/EIPS/eip-2124>.
+
+use crate::errors::{CustomError, MaliciousReason};
+use reth_eth_wire::ForkId;
+
+/// A minimal, dependency-free CRC32 (IEEE 802.3) accumulator.
+///
+/// `finish` can be called mid-stream without disturbing the running state, so the same
+/// digest can be reused to compute the checksum at each fork boundary in sequence.
+struct Crc32Digest(u32);
+
+impl Crc32Digest {
+    fn new() -> Self {
+        Self(0xFFFF_FFFF)
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        const POLY: u32 = 0xEDB8_8320;
+        let mut crc = self.0;
+        for &byte in data {
+            crc ^= byte as u32;
+            for _ in 0..8 {
+                let mask = (crc & 1).wrapping_neg();
+                crc = (crc >> 1) ^ (POLY & mask);
+            }
+        }
+        self.0 = crc;
+    }
+
+    fn finish(&self) -> u32 {
+        !self.0
+    }
+}
+
+/// Knows the ordered list of fork-activation blocks for our chain, and can validate a
+/// peer-advertised [`ForkId`] against it per EIP-2124.
+pub struct ForkFilter {
+    genesis_hash: [u8; 32],
+    fork_blocks: Vec<u64>,
+    head: u64,
+}
+
+impl ForkFilter {
+    /// Builds a filter from a genesis hash, the ordered (deduplicated) list of fork
+    /// block numbers, and our current chain head.
+    pub fn new(genesis_hash: [u8; 32], mut fork_blocks: Vec<u64>, head: u64) -> Self {
+        fork_blocks.sort_unstable();
+        fork_blocks.dedup();
+        Self { genesis_hash, fork_blocks, head }
+    }
+
+    /// The fork id we advertise to peers given our current head.
+    pub fn current(&self) -> ForkId {
+        compute_fork_id(&self.genesis_hash, &self.fork_blocks, self.head)
+    }
+
+    /// The checksum/next pair valid at each point in our fork history: the genesis
+    /// checksum first, then one more entry per fork block, each folding that block's
+    /// big-endian encoding into the running CRC32.
+    fn checksums(&self) -> Vec<ForkId> {
+        let mut digest = Crc32Digest::new();
+        digest.update(&self.genesis_hash);
+
+        let mut sums = Vec::with_capacity(self.fork_blocks.len() + 1);
+        sums.push(ForkId {
+            hash: digest.finish().to_be_bytes().into(),
+            next: self.fork_blocks.first().copied().unwrap_or(0),
+        });
+
+        for (i, block) in self.fork_blocks.iter().enumerate() {
+            digest.update(&block.to_be_bytes());
+            let next = self.fork_blocks.get(i + 1).copied().unwrap_or(0);
+            sums.push(ForkId { hash: digest.finish().to_be_bytes().into(), next });
+        }
+
+        sums
+    }
+
+    /// Validates a peer's advertised fork id against our own fork history, per the
+    /// EIP-2124 ruleset:
+    ///
+    /// 1. If the remote's checksum matches our *current* one, accept unconditionally —
+    ///    we're in the same fork state now, and a `next` mismatch only means we know of
+    ///    different future forks, which isn't relevant until one of them triggers.
+    /// 2. If the remote's checksum matches one of our *past* checksums, it's syncing and
+    ///    behind us; accept only if it correctly names the fork that follows that point
+    ///    in our history as its `next`.
+    /// 3. If the remote's checksum matches one of our *future* checksums, we're the one
+    ///    syncing and it's ahead of us on a fork we already know about; accept
+    ///    unconditionally.
+    /// 4. A checksum that matches nothing in our history is rejected.
+    pub fn validate(&self, remote: ForkId) -> Result<(), CustomError> {
+        let entries = self.checksums();
+        let current_index = self.fork_blocks.iter().filter(|&&block| block <= self.head).count();
+
+        for (i, local) in entries.iter().enumerate() {
+            if local.hash != remote.hash {
+                continue;
+            }
+
+            return match i.cmp(&current_index) {
+                std::cmp::Ordering::Equal | std::cmp::Ordering::Greater => Ok(()),
+                std::cmp::Ordering::Less if local.next == remote.next => Ok(()),
+                std::cmp::Ordering::Less => {
+                    Err(CustomError::Malicious(MaliciousReason::StatusMismatch(format!(
+                        "peer fork id {remote:?} is stale relative to our fork history"
+                    ))))
+                }
+            };
+        }
+
+        Err(CustomError::Malicious(MaliciousReason::StatusMismatch(format!(
+            "peer fork id {remote:?} does not match any checksum in our fork history"
+        ))))
+    }
+}
+
+/// Computes the fork id we advertise for a chain with the given `genesis_hash` and the
+/// ordered list of fork block numbers, given our current chain `head`.
+///
+/// Per EIP-2124 the checksum starts as the CRC32 of the genesis hash and folds in the
+/// big-endian encoding of each fork block that is at or before `head`. `next` is the
+/// first not-yet-applied fork block, or `0` if we know of none.
+pub fn compute_fork_id(genesis_hash: &[u8; 32], fork_blocks: &[u64], head: u64) -> ForkId {
+    let mut digest = Crc32Digest::new();
+    digest.update(genesis_hash);
+
+    let mut next = 0u64;
+    for &block in fork_blocks {
+        if block <= head {
+            digest.update(&block.to_be_bytes());
+        } else {
+            next = block;
+            break;
+        }
+    }
+
+    ForkId { hash: digest.finish().to_be_bytes().into(), next }
+}
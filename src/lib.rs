@@ -0,0 +1,15 @@
+//! Library interface for p2phandshake.
+//!
+//! This exposes the building blocks used by the CLI binary (and by the integration
+//! tests) for parsing node records and driving the RLPx/eth handshake.
+
+pub mod chain;
+pub mod cli;
+pub mod config;
+pub mod discovery;
+pub mod errors;
+pub mod forkid;
+pub mod network;
+pub mod node;
+pub mod reputation;
+pub mod scan;
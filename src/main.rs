@@ -1,121 +1,238 @@
-use std::{str::FromStr, time::Duration};
-
-use alloy_rlp::{Decodable, Encodable};
 use clap::Parser;
-use futures::{SinkExt, StreamExt};
-use reth_ecies::stream::ECIESStream;
-use reth_eth_wire::{DisconnectReason, HelloMessage, P2PMessage};
-use reth_network_peers::{pk2id, NodeRecord};
-use secp256k1::{rand, SecretKey, SECP256K1};
-use tokio::net::TcpStream;
-
-mod errors;
-use errors::CustomError;
-
-/// Struct for parsing command line arguments
-#[derive(Parser, Debug)]
-#[command(name = "Node Connector")]
-#[command(about = "A CLI for connecting to an Ethereum node", long_about = None)]
-struct Cli {
-    /// NodeRecord string
-    #[arg(long)]
-    node_record: String,
-}
+use p2phandshake::{
+    chain::ChainInfo,
+    cli::Cli,
+    discovery,
+    errors::CustomError,
+    network::{self, HandshakeRole},
+    reputation::{self, ReputationTracker},
+    scan::{self, ScanSummary},
+};
+use reth_eth_wire::DisconnectReason;
+use reth_network_peers::NodeRecord;
+use secp256k1::{rand, SecretKey};
+use std::{str::FromStr, sync::Arc, time::Duration};
+use tokio::net::TcpListener;
+use tokio::sync::Semaphore;
+
+/// Maximum number of inbound connections `run_listener` will carry a handshake through to
+/// completion for at once; anything beyond that is told `TooManyPeers` and dropped.
+const MAX_INBOUND_PEERS: usize = 50;
 
-fn parse_node_record(node_record_str: &str) -> Result<(&str, u16), CustomError> {
-    const ETH_EXPECTED_PARTS_LEN: usize = 2;
+#[tokio::main]
+async fn main() -> Result<(), CustomError> {
+    let cli = Cli::parse();
+    let chain = ChainInfo::mainnet();
 
-    let parts: Vec<&str> = node_record_str.split('@').collect();
-    if parts.len() != ETH_EXPECTED_PARTS_LEN {
-        return Err(CustomError::AddressPortParse);
+    if let Some(listen_addr) = &cli.listen {
+        return run_listener(listen_addr, &chain).await;
     }
 
-    let address_port: Vec<&str> = parts[1].split(':').collect();
-    if address_port.len() != ETH_EXPECTED_PARTS_LEN {
-        return Err(CustomError::AddressPortParse);
+    if let Some(ping_interval_secs) = cli.keepalive {
+        return run_keepalive(&cli, ping_interval_secs).await;
     }
 
-    let addr = address_port[0];
-    let port: u16 = address_port[1]
-        .parse()
-        .map_err(|_| CustomError::AddressPortParse)?;
+    if cli.discover {
+        return run_discovery(&cli, &chain).await;
+    }
 
-    Ok((addr, port))
-}
+    let node_records = collect_node_records(&cli)?;
+    if node_records.is_empty() {
+        return Err(CustomError::Unexpected(
+            "no node records given; pass --node-record, --node-record-file, or --discover".to_string(),
+        ));
+    }
 
-async fn create_client_stream(
-    addr: &str,
-    port: u16,
-    node_record_str: &str,
-    secret_key: &SecretKey,
-) -> Result<ECIESStream<TcpStream>, CustomError> {
-    let outgoing = TcpStream::connect((addr, port))
-        .await
-        .map_err(CustomError::TcpConnect)?;
-    let node = NodeRecord::from_str(node_record_str).map_err(CustomError::NodeRecordCreation)?;
-    ECIESStream::connect(outgoing, *secret_key, node.id)
-        .await
-        .map_err(|_| CustomError::ECIESStreamCreation)
+    let secret_key = SecretKey::new(&mut rand::thread_rng());
+    let summary = scan::scan_peers(node_records, &secret_key, &chain, cli.concurrency).await;
+    report_summary(&summary, cli.json);
+
+    Ok(())
 }
 
-async fn send_message(
-    client_stream: &mut ECIESStream<TcpStream>,
-    message: P2PMessage,
-) -> Result<(), CustomError> {
-    let mut encoded_msg = Vec::new();
-    message.encode(&mut encoded_msg);
+/// Collects node records from `--node-record` and `--node-record-file`, in that order.
+fn collect_node_records(cli: &Cli) -> Result<Vec<NodeRecord>, CustomError> {
+    let mut urls = cli.node_record.clone();
+
+    if let Some(path) = &cli.node_record_file {
+        let contents = std::fs::read_to_string(path)?;
+        urls.extend(
+            contents
+                .lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty() && !line.starts_with('#'))
+                .map(str::to_string),
+        );
+    }
 
-    client_stream
-        .send(encoded_msg.into())
-        .await
-        .map_err(|_| CustomError::SendMessage)
+    urls.iter()
+        .map(|url| NodeRecord::from_str(url).map_err(CustomError::from))
+        .collect()
 }
 
-async fn send_hello_message(
-    client_stream: &mut ECIESStream<TcpStream>,
-    secret_key: &SecretKey,
-) -> Result<(), CustomError> {
-    let our_peer_id = pk2id(&secret_key.public_key(SECP256K1));
-    let msg = HelloMessage::builder(our_peer_id).build().into_message();
+/// Prints a [`ScanSummary`] either as JSON or as human-readable lines, depending on `json`.
+fn report_summary(summary: &ScanSummary, json: bool) {
+    if json {
+        match serde_json::to_string_pretty(summary) {
+            Ok(rendered) => println!("{rendered}"),
+            Err(e) => eprintln!("Failed to serialize scan summary: {e}"),
+        }
+        return;
+    }
 
-    let hello = P2PMessage::Hello(msg);
-    send_message(client_stream, hello).await
+    println!("Scanned {} peers: {} succeeded, {} failed", summary.total, summary.successes, summary.failures);
+    for peer in &summary.peers {
+        if peer.success {
+            println!(
+                "  OK   {} client={:?} protocol={:?} caps={:?} port={:?}",
+                peer.node_record, peer.client_version, peer.protocol_version, peer.capabilities, peer.listen_port
+            );
+        } else {
+            println!("  FAIL {} error={:?}", peer.node_record, peer.error);
+        }
+    }
 }
 
-async fn send_disconnect_message(
-    client_stream: &mut ECIESStream<TcpStream>,
-) -> Result<(), CustomError> {
-    let disconnect = P2PMessage::Disconnect(DisconnectReason::ClientQuitting);
-    send_message(client_stream, disconnect).await
-}
+/// Crawls for peers via discv4 and performs a handshake with each one discovered, until
+/// `cli.target_peers` handshakes have succeeded.
+async fn run_discovery(cli: &Cli, chain: &ChainInfo) -> Result<(), CustomError> {
+    if cli.target_peers == 0 {
+        return Err(CustomError::Unexpected(
+            "--target-peers must be at least 1".to_string(),
+        ));
+    }
 
-async fn receive_p2p_message(
-    client_stream: &mut ECIESStream<TcpStream>,
-) -> Result<P2PMessage, CustomError> {
-    let message_result = tokio::time::timeout(Duration::from_millis(1000), client_stream.next())
+    let secret_key = SecretKey::new(&mut rand::thread_rng());
+    let mut discovered =
+        discovery::discover_peers(&secret_key, &cli.bootnodes, cli.target_peers * 4).await?;
+
+    let mut reputation = ReputationTracker::new();
+    let mut handshakes_done = 0;
+    while handshakes_done < cli.target_peers {
+        let Some(record) = discovered.recv().await else {
+            eprintln!("Discovery stream ended with {handshakes_done}/{} handshakes done", cli.target_peers);
+            break;
+        };
+
+        if reputation.is_disabled(&record.id) {
+            eprintln!("Skipping {record}, reputation score {} is below threshold", reputation.score(&record.id));
+            continue;
+        }
+
+        let node_record_str = format!("enode://{}@{}:{}", record.id, record.address, record.tcp_port);
+        let stream = match network::create_client_stream(
+            &record.address.to_string(),
+            record.tcp_port,
+            &node_record_str,
+            &secret_key,
+        )
         .await
-        .map_err(|_| CustomError::ReceiveMessage)?;
-
-    let message = message_result.ok_or(CustomError::ReceiveMessage)??;
+        {
+            Ok(stream) => stream,
+            Err(e) => {
+                eprintln!("Failed to connect to {record}: {e}");
+                continue;
+            }
+        };
+
+        match network::handshake(stream, &secret_key, chain, HandshakeRole::Initiator).await {
+            Ok(outcome) => {
+                reputation.record_success(record.id);
+                println!("Handshake succeeded with {record}: {:?}", outcome.peer_hello);
+                handshakes_done += 1;
+            }
+            Err(e) => {
+                if let Some(fault) = reputation::classify_error(&e) {
+                    let punishment = reputation.record_fault(record.id, fault);
+                    eprintln!("Handshake failed with {record}: {e} (fault: {fault:?}, punishment: {punishment:?})");
+                } else {
+                    eprintln!("Handshake failed with {record}: {e}");
+                }
+            }
+        }
+    }
 
-    let resp = P2PMessage::decode(&mut &message[..])?;
-    Ok(resp)
+    Ok(())
 }
 
-#[tokio::main]
-async fn main() -> Result<(), CustomError> {
-    let cli = Cli::parse();
-    let node_record_str = &cli.node_record;
-
-    let (addr, port) = parse_node_record(node_record_str)?;
-
+/// Dials the first `--node-record` and runs a Hello + Ping/Pong keepalive session
+/// against it, pinging every `ping_interval_secs` seconds until the peer disconnects.
+async fn run_keepalive(cli: &Cli, ping_interval_secs: u64) -> Result<(), CustomError> {
+    let node_record_str = cli.node_record.first().ok_or_else(|| {
+        CustomError::Unexpected("--keepalive requires at least one --node-record".to_string())
+    })?;
+    let node_record = NodeRecord::from_str(node_record_str)?;
     let secret_key = SecretKey::new(&mut rand::thread_rng());
-    let mut client_stream = create_client_stream(addr, port, node_record_str, &secret_key).await?;
 
-    send_hello_message(&mut client_stream, &secret_key).await?;
-    let resp = receive_p2p_message(&mut client_stream).await?;
+    let client_stream = network::create_client_stream(
+        &node_record.address.to_string(),
+        node_record.tcp_port,
+        node_record_str,
+        &secret_key,
+    )
+    .await?;
+
+    let ping_interval = Duration::from_secs(ping_interval_secs.max(1));
+    let pong_deadline = Duration::from_secs((ping_interval_secs / 2).max(2));
 
-    send_disconnect_message(&mut client_stream).await?;
+    println!("Starting keepalive session with {node_record} (ping every {ping_interval_secs}s)...");
+    let reason =
+        network::run_keepalive_session(client_stream, &secret_key, ping_interval, pong_deadline).await?;
+    println!("Connection ended: {reason:?}");
 
     Ok(())
 }
+
+/// Binds `listen_addr` and accepts inbound connections, running the responder side of
+/// the handshake for each one on its own task.
+///
+/// At most [`MAX_INBOUND_PEERS`] handshakes run at once; a peer accepted beyond that is
+/// told `TooManyPeers` and dropped once its ECIES transport is up, rather than carrying it
+/// through to a full handshake we have no capacity left to service.
+async fn run_listener(listen_addr: &str, chain: &ChainInfo) -> Result<(), CustomError> {
+    let secret_key = SecretKey::new(&mut rand::thread_rng());
+    let listener = TcpListener::bind(listen_addr).await?;
+    println!("Listening on {listen_addr}...");
+
+    let capacity = Arc::new(Semaphore::new(MAX_INBOUND_PEERS));
+
+    loop {
+        let (incoming, peer_addr) = match listener.accept().await {
+            Ok(accepted) => accepted,
+            Err(e) => {
+                eprintln!("Failed to accept connection: {e}");
+                continue;
+            }
+        };
+
+        let secret_key = secret_key;
+        let chain = chain.clone();
+        let permit = Arc::clone(&capacity).try_acquire_owned();
+        tokio::spawn(async move {
+            let mut stream = match network::accept_client_stream(incoming, &secret_key).await {
+                Ok(stream) => stream,
+                Err(e) => {
+                    eprintln!("ECIES handshake with {peer_addr} failed: {e}");
+                    return;
+                }
+            };
+
+            let _permit = match permit {
+                Ok(permit) => permit,
+                Err(_) => {
+                    eprintln!("At capacity ({MAX_INBOUND_PEERS} peers), rejecting {peer_addr}");
+                    let _ =
+                        network::send_disconnect_message(&mut stream, DisconnectReason::TooManyPeers).await;
+                    return;
+                }
+            };
+
+            match network::handshake(stream, &secret_key, &chain, HandshakeRole::Responder).await {
+                Ok(outcome) => {
+                    println!("Accepted handshake from {peer_addr}: {:?}", outcome.peer_hello);
+                }
+                Err(e) => eprintln!("Handshake with {peer_addr} failed: {e}"),
+            }
+        });
+    }
+}
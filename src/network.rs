@@ -3,18 +3,35 @@
 //! This module provides functions for network operations such as creating client streams,
 //! sending and receiving messages, and performing handshake operations with remote Ethereum nodes.
 
-use crate::{config::TIMEOUT, errors::CustomError};
+use crate::{
+    chain::ChainInfo,
+    config::{MAX_FRAME_SIZE, MAX_P2P_PROTOCOL_VERSION, MIN_P2P_PROTOCOL_VERSION, TIMEOUT},
+    errors::{CustomError, MaliciousReason},
+    forkid::ForkFilter,
+};
 
+use alloy_primitives::U256;
 use alloy_rlp::{Decodable, Encodable};
 use futures::{SinkExt, StreamExt};
 use log::{debug, error, info};
 use reth_ecies::stream::ECIESStream;
-use reth_eth_wire::{DisconnectReason, HelloMessage, P2PMessage};
+use reth_eth_wire::{
+    capability::Capability, DisconnectReason, EthVersion, HelloMessage, P2PMessage, P2PStream,
+    Status, UnauthedEthStream,
+};
 use reth_network_peers::{pk2id, NodeRecord};
 use secp256k1::{SecretKey, SECP256K1};
 use std::{str::FromStr, time::Duration};
 use tokio::net::TcpStream;
 
+/// Outcome of a completed handshake: the peer's Hello, the capabilities we negotiated
+/// with them, and - when `eth` was among those capabilities - their validated `Status`.
+pub struct HandshakeOutcome {
+    pub peer_hello: HelloMessage,
+    pub shared_capabilities: Vec<Capability>,
+    pub peer_status: Option<Status>,
+}
+
 /// Creates a client stream for connecting to a remote Ethereum node.
 ///
 /// This function attempts to connect to the provided address and port,
@@ -41,12 +58,36 @@ pub async fn create_client_stream(
         TcpStream::connect((addr, port)),
     )
     .await
-    .map_err(|_| CustomError::TcpConnectTimeOut("Connection timed out".to_string()))??;
+    .map_err(|_| CustomError::Timeout(format!("connecting to {addr}:{port} timed out")))??;
 
-    let node = NodeRecord::from_str(node_record_str).map_err(CustomError::NodeRecordCreation)?;
+    let node = NodeRecord::from_str(node_record_str)?;
     ECIESStream::connect_with_timeout(outgoing, *secret_key, node.id, Duration::from_secs(1))
         .await
-        .map_err(|_| CustomError::ECIESStreamCreation)
+        .map_err(|e| CustomError::Unexpected(format!("ECIES handshake failed: {e}")))
+}
+
+/// Completes the responder side of the ECIES handshake for an inbound TCP connection.
+///
+/// This is the accepting counterpart to [`create_client_stream`]: instead of dialing out,
+/// it takes a connection a [`tokio::net::TcpListener`] has already accepted and performs
+/// the auth/ack exchange as the responder.
+///
+/// # Arguments
+///
+/// * `incoming` - The accepted TCP connection.
+/// * `secret_key` - Our node's secret key, used to authenticate the ECIES handshake.
+///
+/// # Returns
+///
+/// A result containing the established ECIES stream, or a `CustomError` if the
+/// handshake fails.
+pub async fn accept_client_stream(
+    incoming: TcpStream,
+    secret_key: &SecretKey,
+) -> Result<ECIESStream<TcpStream>, CustomError> {
+    ECIESStream::incoming(incoming, *secret_key)
+        .await
+        .map_err(|e| CustomError::Unexpected(format!("ECIES handshake failed: {e}")))
 }
 
 /// Sends a message over the provided client stream.
@@ -67,54 +108,87 @@ pub async fn send_message(
 ) -> Result<(), CustomError> {
     let mut encoded_msg = Vec::new();
     message.encode(&mut encoded_msg);
+    check_frame_size(encoded_msg.len())?;
 
     client_stream
         .send(encoded_msg.into())
         .await
-        .map_err(|_| CustomError::SendMessage)
+        .map_err(|_| CustomError::ConnectionClosed)
 }
 
-/// Sends a Hello message over the provided client stream.
+/// Rejects a frame larger than [`MAX_FRAME_SIZE`] as malicious rather than letting it
+/// force unbounded buffer growth.
+fn check_frame_size(len: usize) -> Result<(), CustomError> {
+    if len > MAX_FRAME_SIZE {
+        return Err(CustomError::Malicious(MaliciousReason::FrameTooLarge {
+            actual: len,
+            max: MAX_FRAME_SIZE,
+        }));
+    }
+    Ok(())
+}
+
+/// Builds the Hello message we advertise to a peer.
 ///
-/// This function constructs a Hello message and sends it over the client stream.
+/// Registers `eth` as a supported protocol so `negotiate_shared_capabilities` has
+/// something to intersect against a peer's Hello - without this, our own capability list
+/// is empty and no peer, however compliant, could ever share the `eth` capability with us.
+fn build_hello_message(secret_key: &SecretKey) -> HelloMessage {
+    let our_peer_id = pk2id(&secret_key.public_key(SECP256K1));
+    HelloMessage::builder(our_peer_id)
+        .protocol(EthVersion::Eth68.into())
+        .build()
+        .into_message()
+}
+
+/// Sends a Hello message over the provided client stream.
 ///
 /// # Arguments
 ///
 /// * `client_stream` - The ECIES client stream.
-/// * `secret_key` - The secret key used for constructing the Hello message.
+/// * `hello` - The Hello message to send.
 ///
 /// # Returns
 ///
 /// A result indicating success or failure, with a `CustomError` if the send operation fails.
 pub async fn send_hello_message(
     client_stream: &mut ECIESStream<TcpStream>,
-    secret_key: &SecretKey,
+    hello: &HelloMessage,
 ) -> Result<(), CustomError> {
-    let our_peer_id = pk2id(&secret_key.public_key(SECP256K1));
-    let msg = HelloMessage::builder(our_peer_id).build().into_message();
-
-    let hello = P2PMessage::Hello(msg);
-    send_message(client_stream, hello).await
+    send_message(client_stream, P2PMessage::Hello(hello.clone())).await
 }
 
-/// Sends a Disconnect message over the provided client stream.
-///
-/// This function constructs a Disconnect message and sends it over the client stream.
+/// Sends a Disconnect message with the given reason over the provided client stream.
 ///
 /// # Arguments
 ///
 /// * `client_stream` - The ECIES client stream.
+/// * `reason` - Why we're disconnecting, e.g. `ClientQuitting` for a clean shutdown or
+///   `ProtocolBreach` when the peer misbehaved.
 ///
 /// # Returns
 ///
 /// A result indicating success or failure, with a `CustomError` if the send operation fails.
 pub async fn send_disconnect_message(
     client_stream: &mut ECIESStream<TcpStream>,
+    reason: DisconnectReason,
 ) -> Result<(), CustomError> {
-    let disconnect = P2PMessage::Disconnect(DisconnectReason::ClientQuitting);
+    let disconnect = P2PMessage::Disconnect(reason);
     send_message(client_stream, disconnect).await
 }
 
+/// Waits for the next P2P message on the stream, with no timeout of its own.
+///
+/// Used directly by callers (like the keepalive loop) that need to wait indefinitely
+/// for the next message alongside other timed events; [`receive_p2p_message`] wraps this
+/// with the crate's default timeout for one-shot receives.
+async fn next_p2p_message(client_stream: &mut ECIESStream<TcpStream>) -> Result<P2PMessage, CustomError> {
+    let message = client_stream.next().await.ok_or(CustomError::ConnectionClosed)??;
+    check_frame_size(message.len())?;
+    let resp = P2PMessage::decode(&mut &message[..])?;
+    Ok(resp)
+}
+
 /// Receives a P2P message from the provided client stream.
 ///
 /// This function waits for a message from the client stream and decodes it.
@@ -129,59 +203,282 @@ pub async fn send_disconnect_message(
 pub async fn receive_p2p_message(
     client_stream: &mut ECIESStream<TcpStream>,
 ) -> Result<P2PMessage, CustomError> {
-    let message_result = tokio::time::timeout(Duration::from_secs(TIMEOUT), client_stream.next())
+    tokio::time::timeout(Duration::from_secs(TIMEOUT), next_p2p_message(client_stream))
         .await
-        .map_err(|_| CustomError::ReceiveMessage)?;
-
-    let message = message_result.ok_or(CustomError::ReceiveMessage)?.unwrap();
+        .map_err(|_| CustomError::Timeout("receiving a P2P message timed out".to_string()))?
+}
 
-    let resp = P2PMessage::decode(&mut &message[..])?;
-    Ok(resp)
+/// Intersects our Hello's capabilities with the peer's, keeping - for each shared
+/// protocol name - the lower of the two advertised versions. This mirrors how devp2p
+/// peers pick a common subprotocol version before any subprotocol messages are sent.
+fn negotiate_shared_capabilities(ours: &[Capability], theirs: &[Capability]) -> Vec<Capability> {
+    ours.iter()
+        .filter_map(|our_cap| {
+            theirs
+                .iter()
+                .find(|their_cap| their_cap.name == our_cap.name)
+                .map(|their_cap| Capability::new(our_cap.name.clone(), our_cap.version.min(their_cap.version)))
+        })
+        .collect()
 }
 
-/// Performs a handshake with the remote Ethereum node.
+/// Runs the eth subprotocol `Status` handshake over an already Hello-negotiated stream.
 ///
-/// This function sends a Hello message, waits for a response, and then sends a Disconnect message.
+/// Wraps `client_stream` in a [`P2PStream`] scoped to the shared capabilities, then drives
+/// reth's [`UnauthedEthStream`] handshake to exchange `Status` messages. The peer's `Status`
+/// is validated against `chain`: a genesis hash mismatch or an incompatible fork id (per
+/// EIP-2124) is rejected as [`CustomError::Malicious`].
 ///
 /// # Arguments
 ///
-/// * `client_stream` - The ECIES client stream.
-/// * `secret_key` - The secret key used for constructing the Hello message.
+/// * `client_stream` - The ECIES stream, after a successful Hello exchange.
+/// * `shared_capabilities` - The capabilities negotiated from both sides' Hello messages.
+/// * `chain` - The chain parameters we advertise and validate the peer against.
 ///
 /// # Returns
 ///
-/// A result indicating success or failure, with a `CustomError` if any part of the handshake fails.
-pub async fn handshake(
+/// The peer's validated `Status`, or a `CustomError` if the exchange or validation fails.
+async fn status_handshake(
+    client_stream: ECIESStream<TcpStream>,
+    shared_capabilities: &[Capability],
+    chain: &ChainInfo,
+) -> Result<Status, CustomError> {
+    let fork_filter = ForkFilter::new(chain.genesis_hash, chain.fork_blocks.clone(), chain.head);
+
+    let status = Status::builder()
+        .version(EthVersion::Eth68 as u8)
+        .chain(chain.network_id)
+        .genesis(chain.genesis_hash.into())
+        .total_difficulty(U256::from(chain.total_difficulty))
+        .blockhash(chain.best_block_hash.into())
+        .forkid(fork_filter.current())
+        .build();
+
+    let p2p_stream = P2PStream::new(client_stream, shared_capabilities.to_vec());
+    let (eth_stream, peer_status) = UnauthedEthStream::new(p2p_stream)
+        .handshake(status)
+        .await
+        .map_err(|e| CustomError::Unexpected(format!("status exchange failed: {e}")))?;
+
+    if peer_status.genesis != status.genesis {
+        let _ = eth_stream.into_inner().disconnect(DisconnectReason::ProtocolBreach).await;
+        return Err(CustomError::Malicious(MaliciousReason::StatusMismatch(
+            "genesis hash does not match ours".to_string(),
+        )));
+    }
+    if let Err(e) = fork_filter.validate(peer_status.forkid) {
+        let _ = eth_stream.into_inner().disconnect(DisconnectReason::ProtocolBreach).await;
+        return Err(e);
+    }
+
+    Ok(peer_status)
+}
+
+/// Sends our Hello message, logging the outcome.
+async fn send_hello(
     client_stream: &mut ECIESStream<TcpStream>,
-    secret_key: &SecretKey,
+    our_hello: &HelloMessage,
 ) -> Result<(), CustomError> {
-    info!("Initiating handshake...");
-
     info!("Sending Hello message...");
-    if let Err(e) = send_hello_message(client_stream, secret_key).await {
+    if let Err(e) = send_hello_message(client_stream, our_hello).await {
         error!("Failed to send Hello message: {:?}", e);
         return Err(e);
     }
     debug!("Hello message sent.");
+    Ok(())
+}
 
-    info!("Waiting for P2P message...");
-    match receive_p2p_message(client_stream).await {
-        Ok(response) => {
-            info!("Received P2P message: {:?}", response);
+/// Waits for the peer's Hello message, rejecting anything else sent first.
+async fn receive_hello(client_stream: &mut ECIESStream<TcpStream>) -> Result<HelloMessage, CustomError> {
+    info!("Waiting for Hello message...");
+    let peer_hello = match receive_p2p_message(client_stream).await {
+        Ok(P2PMessage::Hello(hello))
+            if !(MIN_P2P_PROTOCOL_VERSION..=MAX_P2P_PROTOCOL_VERSION)
+                .contains(&hello.protocol_version) =>
+        {
+            error!("Peer advertised impossible protocol version: {}", hello.protocol_version);
+            let _ = send_disconnect_message(client_stream, DisconnectReason::ProtocolBreach).await;
+            return Err(CustomError::Malicious(MaliciousReason::ImpossibleProtocolVersion(
+                hello.protocol_version,
+            )));
+        }
+        Ok(P2PMessage::Hello(hello)) => hello,
+        Ok(other) => {
+            error!("Expected Hello, peer sent: {:?}", other);
+            let _ = send_disconnect_message(client_stream, DisconnectReason::ProtocolBreach).await;
+            return Err(CustomError::Malicious(MaliciousReason::MessageBeforeHello(format!(
+                "{other:?}"
+            ))));
         }
         Err(e) => {
-            error!("Failed to receive P2P message: {:?}", e);
+            error!("Failed to receive Hello message: {:?}", e);
             return Err(e);
         }
-    }
+    };
+    debug!("Received Hello: {:?}", peer_hello);
+    Ok(peer_hello)
+}
 
-    info!("Sending Disconnect message...");
-    if let Err(e) = send_disconnect_message(client_stream).await {
-        error!("Failed to send Disconnect message: {:?}", e);
-        return Err(e);
-    }
-    debug!("Disconnect message sent.");
+/// Which side of the RLPx handshake we're playing: the dialing initiator sends Hello
+/// first, while the accepting responder waits for the peer's Hello before replying.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HandshakeRole {
+    Initiator,
+    Responder,
+}
+
+/// Exchanges Hello messages in the order dictated by `role`.
+///
+/// # Returns
+///
+/// A tuple of `(our_hello, peer_hello)`, or a `CustomError` if either side of the
+/// exchange fails.
+pub async fn hello_handshake(
+    client_stream: &mut ECIESStream<TcpStream>,
+    secret_key: &SecretKey,
+    role: HandshakeRole,
+) -> Result<(HelloMessage, HelloMessage), CustomError> {
+    let our_hello = build_hello_message(secret_key);
+
+    let peer_hello = match role {
+        HandshakeRole::Initiator => {
+            send_hello(client_stream, &our_hello).await?;
+            receive_hello(client_stream).await?
+        }
+        HandshakeRole::Responder => {
+            let peer_hello = receive_hello(client_stream).await?;
+            send_hello(client_stream, &our_hello).await?;
+            peer_hello
+        }
+    };
+
+    Ok((our_hello, peer_hello))
+}
+
+/// Performs a handshake with the remote Ethereum node.
+///
+/// This exchanges Hello messages (in the order dictated by `role`), and - if `eth` is
+/// among the shared capabilities - continues into the eth `Status` handshake. Peers that
+/// don't share `eth` are disconnected once Hello completes.
+///
+/// # Arguments
+///
+/// * `client_stream` - The ECIES client stream.
+/// * `secret_key` - The secret key used for constructing our Hello message.
+/// * `chain` - The chain parameters to advertise and validate the peer's `Status` against.
+/// * `role` - Whether we dialed the peer (`Initiator`) or accepted their connection (`Responder`).
+///
+/// # Returns
+///
+/// A result containing the [`HandshakeOutcome`] if successful, or a `CustomError` if any
+/// part of the handshake fails.
+pub async fn handshake(
+    mut client_stream: ECIESStream<TcpStream>,
+    secret_key: &SecretKey,
+    chain: &ChainInfo,
+    role: HandshakeRole,
+) -> Result<HandshakeOutcome, CustomError> {
+    info!("Initiating handshake as {:?}...", role);
+
+    let (our_hello, peer_hello) = hello_handshake(&mut client_stream, secret_key, role).await?;
+
+    let shared_capabilities = negotiate_shared_capabilities(&our_hello.capabilities, &peer_hello.capabilities);
+    info!("Negotiated capabilities: {:?}", shared_capabilities);
+
+    let peer_status = if shared_capabilities.iter().any(|cap| cap.name == "eth") {
+        info!("eth capability shared, running Status handshake...");
+        Some(status_handshake(client_stream, &shared_capabilities, chain).await?)
+    } else {
+        info!("No eth capability shared, disconnecting.");
+        send_disconnect_message(&mut client_stream, DisconnectReason::UselessPeer).await?;
+        None
+    };
 
     info!("Handshake completed successfully.");
-    Ok(())
+    Ok(HandshakeOutcome { peer_hello, shared_capabilities, peer_status })
+}
+
+/// Services P2P control messages on an already Hello-negotiated stream until the peer
+/// disconnects or goes quiet.
+///
+/// Responds to an incoming `Ping` with `Pong`, and on `ping_interval` proactively sends
+/// our own `Ping`; if the peer doesn't reply with a `Pong` within `pong_deadline`, that's
+/// treated as a fault and the loop returns an error rather than hanging indefinitely.
+///
+/// # Arguments
+///
+/// * `client_stream` - The ECIES stream, after a successful Hello exchange.
+/// * `ping_interval` - How often we proactively ping the peer.
+/// * `pong_deadline` - How long we wait for a `Pong` after one of our own Pings before
+///   treating the peer as unresponsive.
+///
+/// # Returns
+///
+/// The peer-supplied [`DisconnectReason`] once they hang up, or a `CustomError` if the
+/// peer goes quiet or the stream errors out first.
+pub async fn keepalive_loop(
+    client_stream: &mut ECIESStream<TcpStream>,
+    ping_interval: Duration,
+    pong_deadline: Duration,
+) -> Result<DisconnectReason, CustomError> {
+    let mut ping_timer = tokio::time::interval(ping_interval);
+    ping_timer.tick().await; // first tick fires immediately; skip it so we don't ping right after Hello
+
+    loop {
+        tokio::select! {
+            _ = ping_timer.tick() => {
+                debug!("Sending keepalive Ping...");
+                send_message(client_stream, P2PMessage::Ping).await?;
+
+                let deadline = tokio::time::Instant::now() + pong_deadline;
+                loop {
+                    match tokio::time::timeout_at(deadline, next_p2p_message(client_stream)).await {
+                        Ok(Ok(P2PMessage::Pong)) => {
+                            debug!("Received Pong.");
+                            break;
+                        }
+                        Ok(Ok(P2PMessage::Disconnect(reason))) => return Ok(reason),
+                        Ok(Ok(P2PMessage::Ping)) => {
+                            debug!("Received Ping while awaiting Pong, replying with Pong.");
+                            send_message(client_stream, P2PMessage::Pong).await?;
+                        }
+                        Ok(Ok(other)) => debug!("Ignoring unexpected message while awaiting Pong: {:?}", other),
+                        Ok(Err(e)) => return Err(e),
+                        Err(_) => {
+                            return Err(CustomError::Timeout(
+                                "peer did not respond to Ping before the deadline".to_string(),
+                            ));
+                        }
+                    }
+                }
+            }
+            message = next_p2p_message(client_stream) => {
+                match message? {
+                    P2PMessage::Ping => {
+                        debug!("Received Ping, replying with Pong.");
+                        send_message(client_stream, P2PMessage::Pong).await?;
+                    }
+                    P2PMessage::Pong => debug!("Received unsolicited Pong."),
+                    P2PMessage::Disconnect(reason) => return Ok(reason),
+                    other => debug!("Ignoring unexpected message during keepalive: {:?}", other),
+                }
+            }
+        }
+    }
+}
+
+/// Runs the Hello handshake, then a keepalive Ping/Pong session, over `client_stream`.
+///
+/// # Returns
+///
+/// The peer-supplied [`DisconnectReason`] once the session ends, or a `CustomError` if
+/// the Hello handshake or the keepalive loop fails.
+pub async fn run_keepalive_session(
+    mut client_stream: ECIESStream<TcpStream>,
+    secret_key: &SecretKey,
+    ping_interval: Duration,
+    pong_deadline: Duration,
+) -> Result<DisconnectReason, CustomError> {
+    hello_handshake(&mut client_stream, secret_key, HandshakeRole::Initiator).await?;
+    keepalive_loop(&mut client_stream, ping_interval, pong_deadline).await
 }
@@ -44,18 +44,22 @@ impl Node {
     pub fn parse_node_record(node_record_str: &str) -> Result<(&str, u16), CustomError> {
         let parts: Vec<&str> = node_record_str.split('@').collect();
         if parts.len() != ETH_EXPECTED_PARTS_LEN {
-            return Err(CustomError::AddressPortParse);
+            return Err(CustomError::Unexpected(format!(
+                "invalid node record, expected 'id@address:port': {node_record_str}"
+            )));
         }
 
         let address_port: Vec<&str> = parts[1].split(':').collect();
         if address_port.len() != ETH_EXPECTED_PARTS_LEN {
-            return Err(CustomError::AddressPortParse);
+            return Err(CustomError::Unexpected(format!(
+                "invalid node record, expected 'id@address:port': {node_record_str}"
+            )));
         }
 
         let addr = address_port[0];
-        let port: u16 = address_port[1]
-            .parse()
-            .map_err(|_| CustomError::AddressPortParse)?;
+        let port: u16 = address_port[1].parse().map_err(|_| {
+            CustomError::Unexpected(format!("invalid port in node record: {node_record_str}"))
+        })?;
 
         Ok((addr, port))
     }
@@ -0,0 +1,124 @@
+//! Peer reputation and punishment.
+//!
+//! Mirrors how mature Ethereum network stacks grade misbehavior: an observed fault
+//! docks a peer's score, and once it falls below a threshold we stop retrying that peer
+//! for the rest of the crawl session instead of repeatedly reconnecting to it. A
+//! successful handshake nudges the score back up, so one old fault doesn't follow a
+//! peer around forever.
+
+use crate::errors::{CustomError, MaliciousReason};
+
+use reth_network_peers::PeerId;
+use std::collections::HashMap;
+
+/// The punishment to apply after recording a fault - or a success - against a peer.
+///
+/// This governs only whether/how long we keep retrying a peer within the crawl session
+/// (the caller in `main.rs` logs it, and skips a peer once `is_disabled` reports it); by the
+/// time either is called, `network::handshake` has already sent its own disconnect (or
+/// none) and torn down the connection, so there's no live stream left to act on here.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Punishment {
+    /// Disconnect now; the peer may still be retried later in the crawl.
+    Disconnect,
+    /// Disconnect and stop retrying this peer for the rest of the crawl session.
+    Disable,
+    /// No punishment - the peer's score was nudged back up after a clean handshake.
+    Forgive,
+}
+
+/// A class of observed peer misbehavior or fault, each carrying its own reputation
+/// penalty.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fault {
+    /// The peer sent RLP we couldn't decode.
+    MalformedMessage,
+    /// The peer violated the protocol (e.g. a mismatched `Status`, an impossible protocol
+    /// version, or an oversized frame).
+    ProtocolViolation,
+    /// The handshake didn't complete within the timeout.
+    HandshakeTimeout,
+    /// The peer sent something other than Hello before completing the P2P handshake.
+    UnexpectedMessage,
+}
+
+impl Fault {
+    /// How much to dock a peer's score for this fault.
+    fn penalty(self) -> i32 {
+        match self {
+            Fault::MalformedMessage => 50,
+            Fault::ProtocolViolation => 50,
+            Fault::UnexpectedMessage => 25,
+            Fault::HandshakeTimeout => 10,
+        }
+    }
+}
+
+/// Maps a handshake failure to the reputation fault it represents, or `None` for an I/O
+/// error, a closed connection, or an `Unexpected` error, none of which count against the
+/// peer's score. A timeout does count, as `Fault::HandshakeTimeout`.
+pub fn classify_error(error: &CustomError) -> Option<Fault> {
+    match error {
+        CustomError::Deserialization(_) => Some(Fault::MalformedMessage),
+        CustomError::Malicious(MaliciousReason::MessageBeforeHello(_)) => {
+            Some(Fault::UnexpectedMessage)
+        }
+        CustomError::Malicious(_) => Some(Fault::ProtocolViolation),
+        CustomError::Timeout(_) => Some(Fault::HandshakeTimeout),
+        CustomError::Io(_) | CustomError::ConnectionClosed | CustomError::Unexpected(_) => None,
+    }
+}
+
+/// Score below which a peer is treated as disabled and skipped on future crawl attempts.
+pub const DISABLE_THRESHOLD: i32 = 0;
+
+/// Starting score for a peer we haven't observed any faults from yet.
+const STARTING_SCORE: i32 = 100;
+
+/// Score restored per successful handshake, capped at `STARTING_SCORE`. Without this, a
+/// peer that tripped one transient fault long ago would carry that penalty for the rest
+/// of the crawl session even after repeatedly behaving well.
+const FORGIVE_AMOUNT: i32 = 10;
+
+/// Tracks a per-peer reputation score across a crawl session.
+#[derive(Debug, Default)]
+pub struct ReputationTracker {
+    scores: HashMap<PeerId, i32>,
+}
+
+impl ReputationTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records `fault` against `peer`, returning the punishment to apply.
+    pub fn record_fault(&mut self, peer: PeerId, fault: Fault) -> Punishment {
+        let score = self.scores.entry(peer).or_insert(STARTING_SCORE);
+        *score -= fault.penalty();
+
+        if *score < DISABLE_THRESHOLD {
+            Punishment::Disable
+        } else {
+            Punishment::Disconnect
+        }
+    }
+
+    /// Records a successful handshake with `peer`, nudging its score back up towards
+    /// `STARTING_SCORE` and returning `Punishment::Forgive`.
+    pub fn record_success(&mut self, peer: PeerId) -> Punishment {
+        let score = self.scores.entry(peer).or_insert(STARTING_SCORE);
+        *score = (*score + FORGIVE_AMOUNT).min(STARTING_SCORE);
+        Punishment::Forgive
+    }
+
+    /// Whether `peer` has fallen below the disable threshold and should be skipped.
+    pub fn is_disabled(&self, peer: &PeerId) -> bool {
+        self.scores.get(peer).is_some_and(|score| *score < DISABLE_THRESHOLD)
+    }
+
+    /// The peer's current score, or the starting score if we haven't observed any
+    /// faults from them yet.
+    pub fn score(&self, peer: &PeerId) -> i32 {
+        self.scores.get(peer).copied().unwrap_or(STARTING_SCORE)
+    }
+}
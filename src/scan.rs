@@ -0,0 +1,112 @@
+//! Concurrent multi-peer handshake scanning.
+//!
+//! Runs a handshake against many node records at once using a bounded
+//! `FuturesUnordered` driver, and aggregates the results into a structured summary
+//! suitable for human-readable or JSON output.
+
+use crate::{chain::ChainInfo, config::TIMEOUT, network::{self, HandshakeRole}};
+
+use futures::stream::{FuturesUnordered, StreamExt};
+use reth_network_peers::NodeRecord;
+use secp256k1::SecretKey;
+use serde::Serialize;
+use std::time::Duration;
+
+/// The outcome of a single peer's handshake attempt.
+#[derive(Debug, Serialize)]
+pub struct PeerScanResult {
+    pub node_record: String,
+    pub success: bool,
+    pub client_version: Option<String>,
+    pub protocol_version: Option<u8>,
+    pub capabilities: Vec<String>,
+    pub listen_port: Option<u16>,
+    pub error: Option<String>,
+}
+
+/// The aggregated outcome of scanning a batch of peers.
+#[derive(Debug, Serialize)]
+pub struct ScanSummary {
+    pub total: usize,
+    pub successes: usize,
+    pub failures: usize,
+    pub peers: Vec<PeerScanResult>,
+}
+
+/// Runs a handshake against every record in `node_records`, at most `concurrency` at a
+/// time, and returns the aggregated [`ScanSummary`].
+///
+/// # Arguments
+///
+/// * `node_records` - The peers to dial, one handshake task per record.
+/// * `secret_key` - The identity used for every handshake.
+/// * `chain` - The chain parameters advertised in each eth `Status` handshake.
+/// * `concurrency` - The maximum number of handshakes running at once.
+pub async fn scan_peers(
+    node_records: Vec<NodeRecord>,
+    secret_key: &SecretKey,
+    chain: &ChainInfo,
+    concurrency: usize,
+) -> ScanSummary {
+    let mut pending = node_records.into_iter();
+    let mut in_flight = FuturesUnordered::new();
+    let mut results = Vec::new();
+
+    for record in pending.by_ref().take(concurrency.max(1)) {
+        in_flight.push(handshake_one(record, secret_key, chain));
+    }
+
+    while let Some(result) = in_flight.next().await {
+        results.push(result);
+        if let Some(record) = pending.next() {
+            in_flight.push(handshake_one(record, secret_key, chain));
+        }
+    }
+
+    let successes = results.iter().filter(|r| r.success).count();
+    let failures = results.len() - successes;
+    ScanSummary { total: results.len(), successes, failures, peers: results }
+}
+
+/// Dials and handshakes a single peer, translating any failure or timeout into a
+/// [`PeerScanResult`] rather than propagating it, so one bad peer doesn't stop the scan.
+async fn handshake_one(record: NodeRecord, secret_key: &SecretKey, chain: &ChainInfo) -> PeerScanResult {
+    let node_record = record.to_string();
+
+    let attempt = async {
+        let addr = record.address.to_string();
+        let stream =
+            network::create_client_stream(&addr, record.tcp_port, &node_record, secret_key).await?;
+        network::handshake(stream, secret_key, chain, HandshakeRole::Initiator).await
+    };
+
+    match tokio::time::timeout(Duration::from_secs(TIMEOUT), attempt).await {
+        Ok(Ok(outcome)) => PeerScanResult {
+            node_record,
+            success: true,
+            client_version: Some(outcome.peer_hello.client_version.clone()),
+            protocol_version: Some(outcome.peer_hello.protocol_version),
+            capabilities: outcome
+                .shared_capabilities
+                .iter()
+                .map(|cap| format!("{}/{}", cap.name, cap.version))
+                .collect(),
+            listen_port: Some(outcome.peer_hello.port),
+            error: None,
+        },
+        Ok(Err(e)) => failed_result(node_record, e.to_string()),
+        Err(_) => failed_result(node_record, "handshake timed out".to_string()),
+    }
+}
+
+fn failed_result(node_record: String, error: String) -> PeerScanResult {
+    PeerScanResult {
+        node_record,
+        success: false,
+        client_version: None,
+        protocol_version: None,
+        capabilities: Vec::new(),
+        listen_port: None,
+        error: Some(error),
+    }
+}
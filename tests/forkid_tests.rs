@@ -0,0 +1,74 @@
+use p2phandshake::forkid::{compute_fork_id, ForkFilter};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GENESIS: [u8; 32] = [0u8; 32];
+
+    #[test]
+    fn test_compute_fork_id_before_any_fork() {
+        let fork_id = compute_fork_id(&GENESIS, &[100, 200], 0);
+        assert_eq!(fork_id.next, 100);
+    }
+
+    #[test]
+    fn test_compute_fork_id_after_all_forks() {
+        let fork_id = compute_fork_id(&GENESIS, &[100, 200], 250);
+        assert_eq!(fork_id.next, 0);
+    }
+
+    #[test]
+    fn test_fork_filter_accepts_identical_fork_id() {
+        let filter = ForkFilter::new(GENESIS, vec![100, 200], 0);
+        assert!(filter.validate(filter.current()).is_ok());
+    }
+
+    #[test]
+    fn test_fork_filter_rejects_unknown_checksum() {
+        let filter = ForkFilter::new(GENESIS, vec![100, 200], 0);
+        let bogus = compute_fork_id(&[1u8; 32], &[100, 200], 0);
+        assert!(filter.validate(bogus).is_err());
+    }
+
+    #[test]
+    fn test_fork_filter_accepts_peer_unaware_of_unactivated_future_fork() {
+        // We're past block 100 and know of a not-yet-activated fork at 200; the peer's
+        // history stops at 100, so it reports the same checksum but `next: 0` instead of
+        // our `next: 200`. Per EIP-2124 rule 1, matching on our *current* checksum is
+        // enough to connect regardless of the `next` mismatch.
+        let filter = ForkFilter::new(GENESIS, vec![100, 200], 150);
+        let peer = compute_fork_id(&GENESIS, &[100], 150);
+        assert!(filter.validate(peer).is_ok());
+    }
+
+    #[test]
+    fn test_fork_filter_accepts_stale_peer_that_correctly_announces_our_next_fork() {
+        // We're past both forks; the peer's checksum only reflects block 100 (it hasn't
+        // synced to 200 yet), but it correctly names 200 as its next fork - per EIP-2124
+        // rule 2, a stale-but-self-aware peer is compatible.
+        let filter = ForkFilter::new(GENESIS, vec![100, 200], 250);
+        let peer = compute_fork_id(&GENESIS, &[100, 200], 150);
+        assert!(filter.validate(peer).is_ok());
+    }
+
+    #[test]
+    fn test_fork_filter_rejects_stale_peer_unaware_of_our_next_fork() {
+        // Same as above, except the peer doesn't know fork 200 exists at all, so it
+        // reports `next: 0` instead of the 200 we know comes next at that checksum - per
+        // EIP-2124 rule 2, that mismatch makes it incompatible.
+        let filter = ForkFilter::new(GENESIS, vec![100, 200], 250);
+        let peer = compute_fork_id(&GENESIS, &[100], 150);
+        assert!(filter.validate(peer).is_err());
+    }
+
+    #[test]
+    fn test_fork_filter_accepts_peer_ahead_on_a_known_future_fork() {
+        // We're before fork 100; the peer is past 100 but not yet at 200, so its checksum
+        // matches one of our own *future* checksums. Per EIP-2124 rule 3, we accept since
+        // we already know about the fork it's ahead on.
+        let filter = ForkFilter::new(GENESIS, vec![100, 200], 50);
+        let peer = compute_fork_id(&GENESIS, &[100, 200], 150);
+        assert!(filter.validate(peer).is_ok());
+    }
+}
@@ -0,0 +1,73 @@
+use p2phandshake::reputation::{Fault, Punishment, ReputationTracker, DISABLE_THRESHOLD};
+use reth_network_peers::NodeRecord;
+use std::str::FromStr;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn peer_id(byte: u8) -> reth_network_peers::PeerId {
+        let id_hex = format!("{byte:02x}").repeat(64);
+        let node_record_str = format!("enode://{id_hex}@127.0.0.1:30303");
+        NodeRecord::from_str(&node_record_str).unwrap().id
+    }
+
+    #[test]
+    fn test_record_fault_stays_disconnect_above_threshold() {
+        let mut tracker = ReputationTracker::new();
+        let peer = peer_id(1);
+
+        let punishment = tracker.record_fault(peer, Fault::HandshakeTimeout);
+
+        assert_eq!(punishment, Punishment::Disconnect);
+        assert!(!tracker.is_disabled(&peer));
+    }
+
+    #[test]
+    fn test_record_fault_crosses_disable_threshold() {
+        let mut tracker = ReputationTracker::new();
+        let peer = peer_id(2);
+
+        // 100 starting score, docked 50 + 50 + 10: the third fault pushes it below 0.
+        tracker.record_fault(peer, Fault::MalformedMessage);
+        tracker.record_fault(peer, Fault::ProtocolViolation);
+        let punishment = tracker.record_fault(peer, Fault::HandshakeTimeout);
+
+        assert_eq!(punishment, Punishment::Disable);
+        assert!(tracker.score(&peer) < DISABLE_THRESHOLD);
+        assert!(tracker.is_disabled(&peer));
+    }
+
+    #[test]
+    fn test_record_success_caps_at_starting_score() {
+        let mut tracker = ReputationTracker::new();
+        let peer = peer_id(3);
+
+        let punishment = tracker.record_success(peer);
+
+        assert_eq!(punishment, Punishment::Forgive);
+        assert_eq!(tracker.score(&peer), 100);
+    }
+
+    #[test]
+    fn test_record_success_recovers_score_after_a_fault() {
+        let mut tracker = ReputationTracker::new();
+        let peer = peer_id(4);
+
+        tracker.record_fault(peer, Fault::HandshakeTimeout);
+        let score_after_fault = tracker.score(&peer);
+        tracker.record_success(peer);
+
+        assert!(tracker.score(&peer) > score_after_fault);
+        assert!(!tracker.is_disabled(&peer));
+    }
+
+    #[test]
+    fn test_is_disabled_false_for_unknown_peer() {
+        let tracker = ReputationTracker::new();
+        let peer = peer_id(5);
+
+        assert!(!tracker.is_disabled(&peer));
+        assert_eq!(tracker.score(&peer), 100);
+    }
+}